@@ -1,19 +1,161 @@
 use aho_corasick::AhoCorasick;
 use anyhow::Result;
 use bincode::config;
+use memmap2::Mmap;
+use rkyv::Deserialize as ArchiveDeserialize;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 use tokio::fs;
 use tracing::{info, warn};
 
+/// 索引文件格式标记：写在文件首字节，用于区分新旧持久化格式
+///
+/// 旧版本（chunk0-6之前）的`ac_index.bin`没有这个标记字节，文件内容本身就是
+/// 无标记的bincode数据；因此加载时若首字节不是下面的已知标记，就把整份数据
+/// 当作这种旧格式回退处理。
+const FORMAT_TAG_RKYV: u8 = 0xA1;
+
+/// 敏感词的严重级别，从`dic.txt`第三列解析，缺省为`Low`
+///
+/// 声明顺序即严重程度顺序，`min_severity`过滤依赖这个派生的`Ord`实现。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    /// 解析`dic.txt`中第三列的严重级别，无法识别的值按`Low`处理
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "high" => Severity::High,
+            "medium" | "mid" => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+
+    /// 小写字符串表示，用于对外输出（如`CheckResponse`）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Severity::Low
+    }
+}
+
+/// 敏感词未指定分类时的默认分类名
+pub const DEFAULT_CATEGORY: &str = "default";
+
+/// 占位符左右定界符，选用生僻Unicode字符以降低与原文冲突的概率
+const PLACEHOLDER_OPEN: char = '\u{27e6}';
+const PLACEHOLDER_CLOSE: char = '\u{27e7}';
+/// 定界符的转义前缀，用于原文中本就出现定界符的极端情况
+const PLACEHOLDER_ESCAPE: char = '\\';
+
+/// 占位符 -> 原始子串 的还原表
+pub type RestoreMap = HashMap<String, String>;
+
+/// 转义原文中出现的定界符，避免其与占位符混淆
+pub(crate) fn escape_delimiters(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == PLACEHOLDER_OPEN || c == PLACEHOLDER_CLOSE || c == PLACEHOLDER_ESCAPE {
+            escaped.push(PLACEHOLDER_ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 还原被转义的定界符
+fn unescape_delimiters(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == PLACEHOLDER_ESCAPE {
+            if let Some(&next) = chars.peek() {
+                if next == PLACEHOLDER_OPEN || next == PLACEHOLDER_CLOSE || next == PLACEHOLDER_ESCAPE {
+                    unescaped.push(next);
+                    chars.next();
+                    continue;
+                }
+            }
+        }
+        unescaped.push(c);
+    }
+    unescaped
+}
+
+/// 索引文件的rkyv归档载荷：词表及其分类/严重级别三个平行列表
+///
+/// 严重级别以字符串归档（复用`Severity::as_str`/`parse`），这样无需为
+/// `Severity`单独派生rkyv的`Archive`/`Serialize`/`Deserialize`。
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+struct PersistedIndex {
+    words: Vec<String>,
+    categories: Vec<String>,
+    severities: Vec<String>,
+}
+
+/// 基于一组已确定的匹配跨度，把`escaped_text`中对应的子串替换为唯一占位符
+///
+/// 跨度必须按起始位置升序、互不重叠（`SensitiveFilter::find_all_matches`已经
+/// 完成排序去重），且是在`escaped_text`（而非原始未转义文本）上的字节偏移，
+/// 这样才能正确处理原文中本就出现定界符的边界情况。抽成自由函数是为了让调
+/// 用方可以传入AC词典与正则规则引擎合并后的匹配列表，而不必局限于词典命中。
+pub(crate) fn desensitize_spans(escaped_text: &str, spans: &[(usize, usize)]) -> (String, RestoreMap) {
+    let mut result = String::with_capacity(escaped_text.len());
+    let mut map = RestoreMap::new();
+    let mut last = 0;
+    for (idx, &(start, end)) in spans.iter().enumerate() {
+        // 拼接上一个匹配结束到当前匹配开始之间的原文
+        result.push_str(&escaped_text[last..start]);
+        // 生成唯一占位符，例如 ⟦SW_0⟧
+        let placeholder = format!("{PLACEHOLDER_OPEN}SW_{idx}{PLACEHOLDER_CLOSE}");
+        map.insert(placeholder.clone(), escaped_text[start..end].to_string());
+        result.push_str(&placeholder);
+        last = end;
+    }
+    // 拼接最后一个匹配之后剩余的原文
+    result.push_str(&escaped_text[last..]);
+    (result, map)
+}
+
+/// 一次词典匹配，携带命中词的分类与严重级别
+pub struct WordMatch<'a> {
+    pub start: usize,
+    pub end: usize,
+    pub word: &'a str,
+    pub category: &'a str,
+    pub severity: Severity,
+}
+
 /// AC自动机封装，用于敏感词匹配
 #[derive(Serialize, Deserialize)]
 pub struct AcMachine {
     // 存储所有敏感词的集合
     words: Vec<String>,
+    // 与words一一对应的分类，缺省为DEFAULT_CATEGORY
+    #[serde(default)]
+    categories: Vec<String>,
+    // 与words一一对应的严重级别，缺省为Severity::Low
+    #[serde(default)]
+    severities: Vec<Severity>,
     // AC自动机实例，使用serde(skip)标记不进行序列化
     #[serde(skip)]
     ac: Option<AhoCorasick>,
+    // 最长敏感词的字节长度，构建时计算，供流式过滤计算安全边界
+    #[serde(skip)]
+    max_pattern_len: usize,
 }
 
 impl AcMachine {
@@ -22,14 +164,35 @@ impl AcMachine {
         // 返回一个空的AcMachine实例
         Self {
             words: Vec::new(),
+            categories: Vec::new(),
+            severities: Vec::new(),
             ac: None,
+            max_pattern_len: 0,
         }
     }
 
-    /// 从敏感词列表构建AC自动机
+    /// 从敏感词列表构建AC自动机，每个词使用默认分类与严重级别
     pub fn from_words(words: Vec<String>) -> Self {
-        // 创建一个包含敏感词的AcMachine实例
-        let mut machine = Self { words, ac: None };
+        let len = words.len();
+        Self::from_entries(
+            words,
+            vec![DEFAULT_CATEGORY.to_string(); len],
+            vec![Severity::default(); len],
+        )
+    }
+
+    /// 从(词, 分类, 严重级别)三个平行列表构建AC自动机
+    pub fn from_entries(words: Vec<String>, categories: Vec<String>, severities: Vec<Severity>) -> Self {
+        debug_assert_eq!(words.len(), categories.len());
+        debug_assert_eq!(words.len(), severities.len());
+        // 创建一个包含敏感词及其分类信息的AcMachine实例
+        let mut machine = Self {
+            words,
+            categories,
+            severities,
+            ac: None,
+            max_pattern_len: 0,
+        };
         // 构建AC自动机
         machine.build();
         // 返回构建好的实例
@@ -46,18 +209,55 @@ impl AcMachine {
         }
         // 记录正在构建AC自动机的信息
         info!("Building AC machine with {} words", self.words.len());
-        // 使用词汇列表构建AC自动机
-        self.ac = Some(AhoCorasick::new(self.words.clone()).unwrap());
+        // 记录最长敏感词的字节长度，供流式过滤计算跨chunk安全边界
+        self.max_pattern_len = self.words.iter().map(|w| w.len()).max().unwrap_or(0);
+        // 借用词汇列表构建AC自动机，避免克隆整个词表
+        self.ac = Some(AhoCorasick::new(&self.words).unwrap());
+    }
+
+    /// 最长敏感词的字节长度
+    pub fn max_pattern_len(&self) -> usize {
+        self.max_pattern_len
+    }
+
+    /// 当前的敏感词列表
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// 与`words()`一一对应的分类列表
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    /// 与`words()`一一对应的严重级别列表
+    pub fn severities(&self) -> &[Severity] {
+        &self.severities
     }
 
-    /// 在文本中查找敏感词
-    pub fn find_matches(&self, text: &str) -> Vec<(usize, usize, &str)> {
+    /// 在文本中查找敏感词，同时返回命中词的分类与严重级别
+    pub fn find_matches(&self, text: &str) -> Vec<WordMatch<'_>> {
         // 检查AC自动机是否已构建
         if let Some(ac) = &self.ac {
             // 使用AC自动机查找所有匹配项
             ac.find_iter(text)
-                // 将匹配项转换为(开始位置,结束位置,匹配词)的元组
-                .map(|mat| (mat.start(), mat.end(), self.words[mat.pattern()].as_str()))
+                .map(|mat| {
+                    let pattern = mat.pattern();
+                    // 分类/严重级别缺失（如旧版索引）时回退到默认值
+                    let category = self
+                        .categories
+                        .get(pattern)
+                        .map(String::as_str)
+                        .unwrap_or(DEFAULT_CATEGORY);
+                    let severity = self.severities.get(pattern).copied().unwrap_or_default();
+                    WordMatch {
+                        start: mat.start(),
+                        end: mat.end(),
+                        word: self.words[pattern].as_str(),
+                        category,
+                        severity,
+                    }
+                })
                 // 收集所有匹配项到向量中
                 .collect()
         } else {
@@ -68,28 +268,136 @@ impl AcMachine {
     }
 
     /// 保存AC机器到文件
+    ///
+    /// 使用rkyv归档词表及其分类/严重级别，文件首字节写入格式标记，后续可通过
+    /// `build_from_mmap`零拷贝访问，不必像bincode那样先反序列化出一份owned的词表。
     pub async fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        // 将AcMachine实例序列化为二进制数据
-        let serialized = bincode::serde::encode_to_vec(&self, config::standard())?;
-        // 将序列化数据写入文件
-        fs::write(path, serialized).await?;
+        // 严重级别以字符串形式归档，避免给Severity单独派生rkyv的Archive/Serialize
+        let persisted = PersistedIndex {
+            words: self.words.clone(),
+            categories: self.categories.clone(),
+            severities: self.severities.iter().map(|s| s.as_str().to_string()).collect(),
+        };
+        // 将词表及其元数据归档为rkyv的二进制表示
+        let archived = rkyv::to_bytes::<_, 4096>(&persisted)
+            .map_err(|e| anyhow::anyhow!("failed to archive word list: {e}"))?;
+        // 格式标记字节 + 零填充 + 归档数据：填充到16字节，使归档数据在页对齐的
+        // mmap映射中保持rkyv校验所要求的对齐
+        const HEADER_LEN: usize = 16;
+        let mut serialized = Vec::with_capacity(HEADER_LEN + archived.len());
+        serialized.push(FORMAT_TAG_RKYV);
+        serialized.resize(HEADER_LEN, 0);
+        serialized.extend_from_slice(&archived);
+        // 先写入临时文件再重命名，原子替换旧的索引文件
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serialized).await?;
+        fs::rename(&tmp_path, path).await?;
         // 成功返回
         Ok(())
     }
 
     /// 从文件加载AC机器
+    ///
+    /// rkyv格式（文件首字节为`FORMAT_TAG_RKYV`）走`load_from_mmap`的零拷贝路径；
+    /// 旧版本（chunk0-6之前）的索引文件没有这个标记字节，此时把整份数据当作
+    /// 无标记的bincode格式回退处理。
     pub async fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        // 从文件读取序列化数据
+        // 只窥探首字节判断格式，rkyv格式避免把整份文件提前读入堆内存
+        if Self::peek_format_tag(path.as_ref()).await? == Some(FORMAT_TAG_RKYV) {
+            return Self::load_from_mmap(path.as_ref());
+        }
+
+        // 旧格式需要整份文件内容：没有可识别的格式标记，按更早版本的无标记bincode格式回退
         let data = fs::read(path).await?;
-        // 将二进制数据反序列化为AcMachine实例
-        let (mut machine, _): (AcMachine, usize) =
+        // 更早版本的AcMachine只序列化了words一个字段，没有分类/严重级别
+        #[derive(Deserialize)]
+        struct LegacyIndex {
+            words: Vec<String>,
+        }
+        let (legacy, _): (LegacyIndex, usize) =
             bincode::serde::decode_from_slice(&data, config::standard())?;
-        // 注释掉的旧版反序列化代码
-        // let mut machine: AcMachine = bincode::deserialize(&data)?;
-        // 重新构建AC自动机（因为ac字段不会被序列化）
-        machine.build();
-        // 返回加载好的实例
-        Ok(machine)
+
+        // 使用默认分类与严重级别重新构建AC自动机（因为ac字段不会被序列化）
+        Ok(AcMachine::from_words(legacy.words))
+    }
+
+    /// 读取文件首字节作为格式标记，不读取文件的其余部分
+    async fn peek_format_tag(path: &Path) -> Result<Option<u8>> {
+        use tokio::io::AsyncReadExt;
+        let mut file = fs::File::open(path).await?;
+        let mut tag = [0u8; 1];
+        let n = file.read(&mut tag).await?;
+        Ok((n == 1).then_some(tag[0]))
+    }
+
+    /// 通过mmap零拷贝加载rkyv格式的索引文件
+    ///
+    /// 只`open`+`mmap`文件一次：自动机直接借用同一份mmap映射出的归档字符串
+    /// 构建，不为每个词先分配一份owned的`String`；分类、严重级别以及`words()`
+    /// 等accessor仍然需要owned数据（供`SensitiveFilter::add_words`/`remove_words`
+    /// 这类读写场景使用），从同一个`archived`视图里按需克隆出来，不再重新打开
+    /// /映射一次文件。这是`SensitiveFilter::init()`实际使用的加载路径。
+    fn load_from_mmap(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: 索引文件在本进程运行期间由我们独占写入（通过save_to_file原子替换），
+        // 不会在映射期间被其他进程并发截断或修改。
+        let mmap = unsafe { Mmap::map(&file)? };
+        let Some((&tag, rest)) = mmap.split_first() else {
+            return Err(anyhow::anyhow!("empty index file"));
+        };
+        if tag != FORMAT_TAG_RKYV {
+            return Err(anyhow::anyhow!(
+                "index file is not in the zero-copy rkyv format"
+            ));
+        }
+        let archived = rkyv::check_archived_root::<PersistedIndex>(rest)
+            .map_err(|e| anyhow::anyhow!("corrupt rkyv index: {e}"))?;
+
+        // 直接借用归档字符串构建自动机，不为每个词分配owned String
+        let ac = AhoCorasick::new(archived.words.iter().map(|s| s.as_str()))?;
+
+        let words = archived.words.iter().map(|s| s.as_str().to_string()).collect();
+        let categories = archived.categories.iter().map(|s| s.as_str().to_string()).collect();
+        let severities = archived
+            .severities
+            .iter()
+            .map(|s| Severity::parse(s.as_str()))
+            .collect();
+        let max_pattern_len = archived.words.iter().map(|s| s.len()).max().unwrap_or(0);
+
+        Ok(Self {
+            words,
+            categories,
+            severities,
+            ac: Some(ac),
+            max_pattern_len,
+        })
+    }
+
+    /// 通过mmap零拷贝访问rkyv归档的索引文件，直接用借用的`&str`构建AC自动机
+    ///
+    /// 独立的单次mmap+构建入口，只需要自动机、不需要owned词表/分类/严重级别
+    /// 时可以直接调用（不会经过`load_from_mmap`那条还要提取owned元数据的路径）。
+    pub fn build_from_mmap<P: AsRef<Path>>(path: P) -> Result<AhoCorasick> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: 索引文件在本进程运行期间由我们独占写入（通过save_to_file原子替换），
+        // 不会在映射期间被其他进程并发截断或修改。
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let Some((&tag, rest)) = mmap.split_first() else {
+            return Err(anyhow::anyhow!("empty index file"));
+        };
+        if tag != FORMAT_TAG_RKYV {
+            return Err(anyhow::anyhow!(
+                "index file is not in the zero-copy rkyv format"
+            ));
+        }
+
+        let archived = rkyv::check_archived_root::<PersistedIndex>(rest)
+            .map_err(|e| anyhow::anyhow!("corrupt rkyv index: {e}"))?;
+        // 直接借用归档字符串构建自动机，不为每个词分配owned String
+        Ok(AhoCorasick::new(archived.words.iter().map(|s| s.as_str()))?)
     }
 
     /// 过滤文本中的敏感词，用*替换
@@ -116,4 +424,109 @@ impl AcMachine {
             text.to_string()
         }
     }
+
+    /// 还原脱敏文本：将占位符替换回映射表中记录的原始子串
+    pub fn restore_text(text: &str, map: &RestoreMap) -> String {
+        // 按出现顺序收集所有能在映射表中找到的占位符跨度
+        let mut spans = Vec::new();
+        let mut search_start = 0;
+        while let Some(open_rel) = text[search_start..].find(PLACEHOLDER_OPEN) {
+            let open = search_start + open_rel;
+            let Some(close_rel) = text[open..].find(PLACEHOLDER_CLOSE) else {
+                break;
+            };
+            let close = open + close_rel + PLACEHOLDER_CLOSE.len_utf8();
+            let token = &text[open..close];
+            if map.contains_key(token) {
+                spans.push((open, close, token.to_string()));
+                search_start = close;
+            } else {
+                // 不是占位符（如原文中被转义的字面定界符），只越过这个左定界符继续查找，
+                // 以免把紧随其后的真实占位符一并跳过
+                search_start = open + PLACEHOLDER_OPEN.len_utf8();
+            }
+        }
+
+        // 从后向前替换，避免位置偏移（与filter_text的替换方式一致）
+        let mut restored = text.to_string();
+        for (start, end, token) in spans.iter().rev() {
+            if let Some(original) = map.get(token) {
+                restored.replace_range(*start..*end, original);
+            }
+        }
+
+        // 还原被转义的定界符
+        unescape_delimiters(&restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 脱敏后再还原应得到与原文完全一致的文本
+    #[test]
+    fn desensitize_restore_roundtrip() {
+        let text = "联系方式：13800138000，备用邮箱a@b.com";
+        let spans = vec![(15, 26), (41, text.len())];
+        let (masked, map) = desensitize_spans(text, &spans);
+
+        // 命中的子串已被占位符替换，原文不再直接出现
+        assert!(!masked.contains("13800138000"));
+        assert!(!masked.contains("a@b.com"));
+
+        let restored = AcMachine::restore_text(&masked, &map);
+        assert_eq!(restored, text);
+    }
+
+    /// 原文中本就出现占位符定界符时，脱敏/还原的转义往返也应无损
+    #[test]
+    fn desensitize_restore_roundtrip_with_literal_delimiter() {
+        let text = "数组记作⟦1,2,3⟧，敏感词是18900001111";
+        let escaped = escape_delimiters(text);
+        let start = escaped.find("18900001111").unwrap();
+        let spans = vec![(start, start + "18900001111".len())];
+        let (masked, map) = desensitize_spans(&escaped, &spans);
+        let restored = AcMachine::restore_text(&masked, &map);
+        assert_eq!(restored, text);
+    }
+
+    fn unique_temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("sensitive_word_ac_test_{}_{}", std::process::id(), name))
+    }
+
+    /// 保存为rkyv格式后应能通过`load_from_file`的mmap路径原样加载回来
+    #[tokio::test]
+    async fn load_from_file_roundtrips_rkyv_format() {
+        let path = unique_temp_path("rkyv_index.bin");
+        let machine = AcMachine::from_entries(
+            vec!["测试词".to_string(), "敏感词".to_string()],
+            vec![DEFAULT_CATEGORY.to_string(), "custom".to_string()],
+            vec![Severity::Low, Severity::High],
+        );
+        machine.save_to_file(&path).await.unwrap();
+
+        let loaded = AcMachine::load_from_file(&path).await.unwrap();
+        assert_eq!(loaded.words(), machine.words());
+        assert_eq!(loaded.categories(), machine.categories());
+        assert_eq!(loaded.find_matches("这是一段包含敏感词的文本").len(), 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// 更早版本的无格式标记bincode索引文件（整份数据即只含words字段的旧版AcMachine）应能回退加载
+    #[tokio::test]
+    async fn load_from_file_falls_back_to_legacy_bincode_format() {
+        let path = unique_temp_path("legacy_index.bin");
+        let legacy_words = vec!["旧版词".to_string()];
+        // 只有一个字段的结构体，其bincode编码与单独编码该字段相同
+        let serialized = bincode::serde::encode_to_vec(&legacy_words, config::standard()).unwrap();
+        // 旧格式没有任何标记字节，文件内容本身就是无标记的bincode数据
+        fs::write(&path, serialized).await.unwrap();
+
+        let loaded = AcMachine::load_from_file(&path).await.unwrap();
+        assert_eq!(loaded.words(), legacy_words.as_slice());
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -1,19 +1,97 @@
-use crate::ac::AcMachine;
+use crate::ac::{self, AcMachine, RestoreMap, Severity, DEFAULT_CATEGORY};
+use crate::rules::RuleEngine;
 use anyhow::Result;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{error, info};
 
+/// 一次命中的敏感内容，可能来自词典匹配，也可能来自正则/GROK规则匹配
+pub struct SensitiveMatch {
+    // 匹配在原文中的起始字节位置
+    pub start: usize,
+    // 匹配在原文中的结束字节位置
+    pub end: usize,
+    // 命中的原始子串
+    pub text: String,
+    // 产生该匹配的规则/角色名，词典命中固定为"dict"
+    pub role: String,
+    // 命中词的分类；规则引擎的匹配以角色名作为分类
+    pub category: String,
+    // 命中词的严重级别；规则引擎命中的结构化PII按约定归为High
+    pub severity: Severity,
+}
+
+/// 词典中的一条词条：词本身及其分类、严重级别
+///
+/// 运行时新增/删除词条时用它承载完整元数据，避免`add_words`/`remove_words`
+/// 退化为纯字符串操作而丢失已有词条的分类与严重级别。
+#[derive(Clone)]
+pub struct DictEntry {
+    pub word: String,
+    pub category: String,
+    pub severity: Severity,
+}
+
+/// 解析`dic.txt`的一行，格式为`word`、`word\tcategory`或`word\tcategory\tseverity`
+///
+/// 省略的列按默认值处理：分类默认为`DEFAULT_CATEGORY`，严重级别默认为`Low`。
+/// 空行返回`None`，由调用方过滤掉。
+fn parse_dic_line(line: &str) -> Option<DictEntry> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let mut parts = line.split('\t').map(str::trim);
+    let word = parts.next()?.to_string();
+    if word.is_empty() {
+        return None;
+    }
+    let category = parts
+        .next()
+        .filter(|s| !s.is_empty())
+        .unwrap_or(DEFAULT_CATEGORY)
+        .to_string();
+    let severity = parts.next().map(Severity::parse).unwrap_or_default();
+    Some(DictEntry {
+        word,
+        category,
+        severity,
+    })
+}
+
+/// 将词条格式化为`dic.txt`的一行：`word\tcategory\tseverity`
+fn format_dic_line(entry: &DictEntry) -> String {
+    format!("{}\t{}\t{}", entry.word, entry.category, entry.severity.as_str())
+}
+
+/// 运行时词表更新（新增/删除）的结果统计
+pub struct DictUpdateResult {
+    // 实际新增的词数（已去重）
+    pub added: usize,
+    // 实际删除的词数
+    pub removed: usize,
+    // 跳过的词数（新增时为已存在的重复词，删除时为词表中不存在的词）
+    pub skipped: usize,
+}
+
 // 敏感词过滤器结构体
 pub struct SensitiveFilter {
     // 使用Arc和RwLock包装AC自动机，支持并发读写
     ac_machine: Arc<RwLock<AcMachine>>,
+    // 结构化PII规则引擎，规则文件不存在时为None
+    rule_engine: Arc<RwLock<Option<RuleEngine>>>,
     // 模型文件目录路径
     models_dir: PathBuf,
     // 敏感词源文件目录路径
     source_dir: PathBuf,
+    // 串行化`add_words`/`remove_words`的读-改-写序列，避免两个并发写者都基于
+    // 同一份旧词表计算增量、后完成的一方覆盖掉先完成的一方的更新（丢失更新）。
+    // 这是与`ac_machine`的`RwLock`分开的独立锁：读请求（过滤/查找）全程不受影响，
+    // 只有写者之间互斥。
+    write_lock: Mutex<()>,
 }
 
 impl SensitiveFilter {
@@ -38,12 +116,16 @@ impl SensitiveFilter {
 
         // 创建空的AC自动机并包装为Arc<RwLock>
         let ac_machine = Arc::new(RwLock::new(AcMachine::new()));
-        
+        // 规则引擎默认未加载，rules.txt存在时由init()负责加载
+        let rule_engine = Arc::new(RwLock::new(None));
+
         // 返回过滤器实例
         Ok(Self {
             ac_machine,
+            rule_engine,
             models_dir,
             source_dir,
+            write_lock: Mutex::new(()),
         })
     }
 
@@ -65,6 +147,8 @@ impl SensitiveFilter {
                     *ac = machine;
                     // 记录加载成功信息
                     info!("Successfully loaded AC index");
+                    // 规则引擎是可选的补充，加载失败不影响词典索引的正常使用
+                    self.load_rules().await;
                     return Ok(());
                 }
                 Err(e) => {
@@ -74,10 +158,30 @@ impl SensitiveFilter {
             }
         }
 
+        // 规则引擎是可选的补充，加载失败不影响词典索引的正常使用
+        self.load_rules().await;
+
         // 如果索引不存在或加载失败，从源文件重建
         self.rebuild_index().await
     }
 
+    /// 从`source_dir/rules.txt`加载正则/GROK规则引擎
+    ///
+    /// 规则文件是可选的，不存在时只使用词典匹配，不视为错误。
+    pub async fn load_rules(&self) {
+        match RuleEngine::load_from_dir(&self.source_dir).await {
+            Ok(engine) => {
+                info!("Loaded rule engine from {:?}", self.source_dir.join("rules.txt"));
+                let mut rules = self.rule_engine.write().await;
+                *rules = Some(engine);
+            }
+            Err(e) => {
+                // rules.txt不存在是正常情况，其他错误(格式问题等)也仅记录日志
+                info!("Rule engine not loaded: {:?}", e);
+            }
+        }
+    }
+
     /// 从源文件重建索引
     pub async fn rebuild_index(&self) -> Result<()> {
         // 构建字典文件路径
@@ -94,23 +198,23 @@ impl SensitiveFilter {
         info!("Building AC index from {:?}", dic_path);
         // 读取字典文件内容
         let content = fs::read_to_string(&dic_path).await?;
-        // 解析字典文件，提取敏感词列表
-        let words: Vec<String> = content
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| line.trim().to_string())
-            .collect();
+        // 解析字典文件，提取敏感词条（含分类、严重级别）
+        let entries: Vec<DictEntry> = content.lines().filter_map(parse_dic_line).collect();
 
         // 检查敏感词列表是否为空
-        if words.is_empty() {
+        if entries.is_empty() {
             return Err(anyhow::anyhow!("No words found in dictionary file"));
         }
 
         // 记录从字典中加载的词数
-        info!("Loaded {} words from dictionary", words.len());
-        // 使用敏感词列表创建AC自动机
-        let machine = AcMachine::from_words(words);
-        
+        info!("Loaded {} words from dictionary", entries.len());
+        // 使用敏感词条创建AC自动机
+        let machine = AcMachine::from_entries(
+            entries.iter().map(|e| e.word.clone()).collect(),
+            entries.iter().map(|e| e.category.clone()).collect(),
+            entries.iter().map(|e| e.severity).collect(),
+        );
+
         // 构建索引文件路径
         let index_path = self.models_dir.join("ac_index.bin");
         // 保存AC自动机到文件
@@ -126,24 +230,454 @@ impl SensitiveFilter {
         Ok(())
     }
 
+    /// 读取当前词表及其分类/严重级别，组装为完整的词条列表
+    async fn current_entries(&self) -> Vec<DictEntry> {
+        let ac = self.ac_machine.read().await;
+        ac.words()
+            .iter()
+            .zip(ac.categories().iter())
+            .zip(ac.severities().iter())
+            .map(|((word, category), severity)| DictEntry {
+                word: word.clone(),
+                category: category.clone(),
+                severity: *severity,
+            })
+            .collect()
+    }
+
+    /// 运行时新增敏感词，无需重启或手工编辑`dic.txt`
+    ///
+    /// 通过API新增的词沿用默认分类与严重级别；如需自定义分类，可直接编辑
+    /// `dic.txt`后调用`/rebuild`。
+    pub async fn add_words(&self, new_words: Vec<String>) -> Result<DictUpdateResult> {
+        // 独占写者身份：与remove_words/另一个并发的add_words互斥，避免两者都基于
+        // 同一份旧词表计算增量，后完成的一方覆盖掉先完成的一方的更新
+        let _guard = self.write_lock.lock().await;
+
+        // 读取当前词表，基于它计算增量
+        let merged_entries = self.current_entries().await;
+        let mut existing: HashSet<String> = merged_entries.iter().map(|e| e.word.clone()).collect();
+
+        let mut merged = merged_entries;
+        let mut added = 0usize;
+        let mut skipped = 0usize;
+        for word in new_words {
+            let word = word.trim().to_string();
+            if word.is_empty() {
+                continue;
+            }
+            // 对新增词去重
+            if existing.insert(word.clone()) {
+                merged.push(DictEntry {
+                    word,
+                    category: DEFAULT_CATEGORY.to_string(),
+                    severity: Severity::default(),
+                });
+                added += 1;
+            } else {
+                skipped += 1;
+            }
+        }
+
+        self.swap_words(merged).await?;
+        info!("Added {} words, skipped {} duplicates", added, skipped);
+        Ok(DictUpdateResult {
+            added,
+            removed: 0,
+            skipped,
+        })
+    }
+
+    /// 运行时删除敏感词，无需重启或手工编辑`dic.txt`
+    pub async fn remove_words(&self, words_to_remove: Vec<String>) -> Result<DictUpdateResult> {
+        // 独占写者身份：与add_words/另一个并发的remove_words互斥，避免两者都基于
+        // 同一份旧词表计算差集，后完成的一方覆盖掉先完成的一方的更新
+        let _guard = self.write_lock.lock().await;
+
+        // 读取当前词表，基于它计算差集
+        let current_entries = self.current_entries().await;
+        let remove_set: HashSet<String> = words_to_remove
+            .into_iter()
+            .map(|w| w.trim().to_string())
+            .collect();
+
+        let mut removed = 0usize;
+        let merged: Vec<DictEntry> = current_entries
+            .into_iter()
+            .filter(|entry| {
+                if remove_set.contains(&entry.word) {
+                    removed += 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect();
+        let skipped = remove_set.len().saturating_sub(removed);
+
+        self.swap_words(merged).await?;
+        info!("Removed {} words, skipped {} not found", removed, skipped);
+        Ok(DictUpdateResult {
+            added: 0,
+            removed,
+            skipped,
+        })
+    }
+
+    /// 用新的词条列表原子替换当前AC自动机，并持久化回`dic.txt`与索引文件
+    ///
+    /// 新的AC自动机在锁外构建，构建期间读请求仍可命中旧索引；只有在新索引
+    /// 构建并落盘完成后，才短暂持有写锁完成替换（对应"构建新的，再原子替换"
+    /// 的热更新模式）。
+    async fn swap_words(&self, entries: Vec<DictEntry>) -> Result<()> {
+        // 在锁外构建新的AC自动机
+        let machine = AcMachine::from_entries(
+            entries.iter().map(|e| e.word.clone()).collect(),
+            entries.iter().map(|e| e.category.clone()).collect(),
+            entries.iter().map(|e| e.severity).collect(),
+        );
+
+        // 持久化更新后的词表到dic.txt，保留分类与严重级别
+        let dic_path = self.source_dir.join("dic.txt");
+        let content = entries.iter().map(format_dic_line).collect::<Vec<_>>().join("\n");
+        fs::write(&dic_path, content).await?;
+
+        // 重新序列化索引文件
+        let index_path = self.models_dir.join("ac_index.bin");
+        machine.save_to_file(&index_path).await?;
+
+        // 构建并持久化完成后，才取写锁做原子替换
+        let mut ac = self.ac_machine.write().await;
+        *ac = machine;
+
+        Ok(())
+    }
+
+    /// 合并词典匹配与规则匹配，按起始位置排序并去除重叠的匹配
+    ///
+    /// 词典匹配与规则匹配可能在同一段文本上重叠命中（例如一串数字既是词典中的
+    /// 敏感词又命中了手机号规则），保留靠前、靠长的匹配；与之重叠的其余匹配
+    /// 不能整条丢弃——只丢弃已被覆盖的前缀部分，未被覆盖的剩余部分要裁剪保留，
+    /// 否则像"词典命中的前两个字符"与"跨越同一位置的完整手机号"重叠时，手机号
+    /// 剩余的大部分数字会被整条匹配一起丢弃，PII几乎原样漏过脱敏/过滤/check。
+    pub(crate) async fn find_all_matches(&self, text: &str) -> Vec<SensitiveMatch> {
+        // 先收集AC词典的匹配，携带词典中记录的分类与严重级别
+        let mut matches: Vec<SensitiveMatch> = {
+            let ac = self.ac_machine.read().await;
+            ac.find_matches(text)
+                .into_iter()
+                .map(|m| SensitiveMatch {
+                    start: m.start,
+                    end: m.end,
+                    text: m.word.to_string(),
+                    role: "dict".to_string(),
+                    category: m.category.to_string(),
+                    severity: m.severity,
+                })
+                .collect()
+        };
+
+        // 再补充规则引擎的匹配（如果已加载）；结构化PII规则命中按约定归为High严重级别，
+        // 分类直接沿用角色名（如"mobile"、"email"）
+        if let Some(engine) = self.rule_engine.read().await.as_ref() {
+            matches.extend(engine.find_matches(text).into_iter().map(|(start, end, role)| {
+                SensitiveMatch {
+                    start,
+                    end,
+                    text: text[start..end].to_string(),
+                    category: role.clone(),
+                    role,
+                    severity: Severity::High,
+                }
+            }));
+        }
+
+        // 按起始位置排序，起始位置相同则更长的匹配排在前面
+        matches.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+
+        // 从前向后扫描：被已保留匹配完全覆盖的整条丢弃；只是前缀被覆盖的，
+        // 裁剪掉重叠的前缀、保留未被覆盖的剩余部分，而不是整条丢弃
+        let mut deduped = Vec::with_capacity(matches.len());
+        let mut cursor = 0usize;
+        for mut m in matches {
+            if m.end <= cursor {
+                continue;
+            }
+            if m.start < cursor {
+                // cursor来自上一个保留匹配的end，与本次匹配的end一样都是
+                // 字符边界（AC/正则匹配的结果），裁剪后重新切片text是安全的
+                m.start = cursor;
+                m.text = text[m.start..m.end].to_string();
+            }
+            cursor = m.end;
+            deduped.push(m);
+        }
+        deduped
+    }
+
     /// 过滤文本中的敏感词
     pub async fn filter(&self, text: &str) -> String {
-        // 获取AC自动机的读锁
-        let ac = self.ac_machine.read().await;
-        // 使用AC自动机过滤文本
-        ac.filter_text(text)
+        // 合并词典匹配与规则匹配
+        let matches = self.find_all_matches(text).await;
+        // 创建文本的可变副本
+        let mut filtered = text.to_string();
+        // 从后向前替换，避免位置偏移
+        for m in matches.iter().rev() {
+            let replacement = "*".repeat(m.end - m.start);
+            filtered.replace_range(m.start..m.end, &replacement);
+        }
+        filtered
     }
 
     /// 查找文本中的敏感词
     pub async fn find_sensitive_words(&self, text: &str) -> Vec<String> {
-        // 获取AC自动机的读锁
-        let ac = self.ac_machine.read().await;
-        // 使用AC自动机查找敏感词
-        ac.find_matches(text)
+        // 合并词典匹配与规则匹配，只保留匹配到的文本，丢弃位置和角色信息
+        self.find_all_matches(text)
+            .await
             .into_iter()
-            // 只保留匹配到的词，丢弃位置信息
-            .map(|(_, _, word)| word.to_string())
-            // 收集到向量中
+            .map(|m| m.text)
             .collect()
     }
+
+    /// 可逆脱敏：将文本中的敏感词替换为唯一占位符，并返回还原映射
+    ///
+    /// 必须基于`find_all_matches`合并后的结果脱敏，而不是只查AC词典——否则
+    /// 正则/GROK规则命中的手机号、邮箱、身份证号等结构化PII会原样穿过本方法，
+    /// 未脱敏就转发给下游服务，违背了脱敏本应提供的边界保护。
+    pub async fn desensitize(&self, text: &str) -> (String, RestoreMap) {
+        // 先转义原文中本就出现的定界符，避免和占位符混淆；后续匹配与替换都基于转义后的文本
+        let escaped = ac::escape_delimiters(text);
+        let spans: Vec<(usize, usize)> = self
+            .find_all_matches(&escaped)
+            .await
+            .into_iter()
+            .map(|m| (m.start, m.end))
+            .collect();
+        ac::desensitize_spans(&escaped, &spans)
+    }
+
+    /// 将脱敏文本中的占位符还原为原始敏感词
+    pub fn restore(&self, text: &str, map: &RestoreMap) -> String {
+        AcMachine::restore_text(text, map)
+    }
+
+    /// 当前可能命中的最大匹配长度，流式过滤据此计算跨chunk安全边界
+    ///
+    /// 取AC词典最长词与规则引擎最长估算命中长度的较大者——流式过滤同时经过
+    /// 两者（见`find_all_matches`），只用词典长度会让更长的规则命中（如邮箱）
+    /// 在跨chunk边界处被从中间切断而漏判。
+    pub async fn max_pattern_len(&self) -> usize {
+        let dict_len = self.ac_machine.read().await.max_pattern_len();
+        let rule_len = self
+            .rule_engine
+            .read()
+            .await
+            .as_ref()
+            .map(|engine| engine.max_pattern_len())
+            .unwrap_or(0);
+        dict_len.max(rule_len)
+    }
+}
+
+/// 跨chunk边界安全的流式过滤会话
+///
+/// 一次性对完整文本调用`filter`/`find_sensitive_words`要求文本已经全部到齐；
+/// 但像LLM输出这样增量到达的文本，若对每个chunk单独过滤，跨越两个chunk的
+/// 敏感词会被从中间切断而漏判。本结构体维护一段至少`max_pattern_len - 1`
+/// 字节的尾部缓冲区：每次只flush确定不会再被后续chunk影响的安全前缀，把
+/// 可能残缺的结尾留到下一个chunk一起处理，直到流结束时一并flush。
+pub struct FilterStream {
+    filter: Arc<SensitiveFilter>,
+    tail: String,
+}
+
+impl FilterStream {
+    /// 创建一个流式过滤会话
+    pub async fn new(filter: Arc<SensitiveFilter>) -> Self {
+        Self {
+            filter,
+            tail: String::new(),
+        }
+    }
+
+    /// 推入一个chunk，返回其中可以安全flush的文本（敏感词已替换为*）
+    pub async fn push(&mut self, chunk: &str) -> String {
+        let flushable = self.take_flushable(chunk).await;
+        self.filter.filter(&flushable).await
+    }
+
+    /// 推入一个chunk，返回可以安全flush的文本中新检测到的敏感词
+    pub async fn push_find(&mut self, chunk: &str) -> Vec<String> {
+        let flushable = self.take_flushable(chunk).await;
+        self.filter.find_sensitive_words(&flushable).await
+    }
+
+    /// 流结束时调用，过滤并flush剩余尾部缓冲
+    pub async fn finish(&mut self) -> String {
+        let tail = std::mem::take(&mut self.tail);
+        self.filter.filter(&tail).await
+    }
+
+    /// 流结束时调用，查找剩余尾部缓冲中的敏感词
+    pub async fn finish_find(&mut self) -> Vec<String> {
+        let tail = std::mem::take(&mut self.tail);
+        self.filter.find_sensitive_words(&tail).await
+    }
+
+    // 将新chunk并入尾部缓冲，返回其中可以安全flush的原始前缀（未过滤）
+    //
+    // carry_len在每个chunk重新从过滤器的当前状态计算，而不是在流创建时冻结一次：
+    // 词典的最长词可能在流处理期间被并发的`/words`新增变长，规则引擎的最长估算
+    // 命中长度也可能比词典更长，两者都必须实时纳入安全边界，否则可能出现命中
+    // 被从中间切断、跨chunk漏判的情况。
+    async fn take_flushable(&mut self, chunk: &str) -> String {
+        self.tail.push_str(chunk);
+        let carry_len = self.filter.max_pattern_len().await.saturating_sub(1);
+
+        // 没有敏感词需要跨chunk保护时，整段都可以安全flush
+        if carry_len == 0 {
+            return std::mem::take(&mut self.tail);
+        }
+
+        // 缓冲区尚不足以确定安全边界，整段留存等待更多chunk
+        if self.tail.len() <= carry_len {
+            return String::new();
+        }
+
+        // 从缓冲区末尾往前留carry_len字节，避免切到跨chunk的敏感词
+        let mut split_at = self.tail.len() - carry_len;
+        // 避免切在UTF-8字符中间
+        while !self.tail.is_char_boundary(split_at) {
+            split_at -= 1;
+        }
+        // 缓冲区内已完整出现、但跨越切分点的命中整条留在尾部，避免被切成两半后漏判
+        for m in self.filter.find_all_matches(&self.tail).await {
+            if m.start < split_at && m.end > split_at {
+                split_at = m.start;
+            }
+        }
+
+        let flushable = self.tail[..split_at].to_string();
+        self.tail = self.tail[split_at..].to_string();
+        flushable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_filter_in_dir(words: &[&str]) -> (SensitiveFilter, PathBuf) {
+        let base_dir = std::env::temp_dir().join(format!(
+            "sensitive_word_filter_test_{}_{}",
+            std::process::id(),
+            words.join("_")
+        ));
+        let filter = SensitiveFilter::new(&base_dir).await.unwrap();
+        let dic_path = base_dir.join("models").join("source").join("dic.txt");
+        fs::write(&dic_path, words.join("\n")).await.unwrap();
+        filter.rebuild_index().await.unwrap();
+        (filter, base_dir)
+    }
+
+    async fn test_filter(words: &[&str]) -> SensitiveFilter {
+        test_filter_in_dir(words).await.0
+    }
+
+    /// 除了词典还加载了正则/GROK规则的过滤器，用于测试词典与规则引擎的合并匹配
+    async fn test_filter_with_rules(words: &[&str], rules: &[&str]) -> SensitiveFilter {
+        let (filter, base_dir) = test_filter_in_dir(words).await;
+        let rules_path = base_dir.join("models").join("source").join("rules.txt");
+        fs::write(&rules_path, rules.join("\n")).await.unwrap();
+        filter.load_rules().await;
+        filter
+    }
+
+    /// 一个敏感词被拆分到两个chunk的边界两侧时，流式过滤也应能识别出来，
+    /// 而不是因为每个chunk单独过滤、命中被从中间切断而漏判
+    #[tokio::test]
+    async fn stream_detects_word_split_across_chunk_boundary() {
+        let filter = Arc::new(test_filter(&["敏感词"]).await);
+        let mut stream = FilterStream::new(filter).await;
+
+        let mut filtered = stream.push("这是一段包含敏").await;
+        filtered.push_str(&stream.push("感词的文本").await);
+        filtered.push_str(&stream.finish().await);
+
+        assert!(!filtered.contains("敏感词"));
+        assert!(filtered.contains('*'));
+    }
+
+    /// 词典命中与规则命中重叠时，重叠部分只裁剪已覆盖的前缀，未覆盖的剩余
+    /// 部分必须保留，不能把整条较长的匹配跟着一起丢弃
+    #[tokio::test]
+    async fn find_all_matches_clips_overlapping_match_instead_of_dropping_it() {
+        let filter = test_filter_with_rules(&["a1"], &["mobile=%{MOBILE}"]).await;
+        // "a1"命中词典(0,2)；"13800138000"命中MOBILE规则(1,12)，两者重叠
+        let text = "a138001380001";
+        let matches = filter.find_all_matches(text).await;
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].start, 0);
+        assert_eq!(matches[0].end, 2);
+        assert_eq!(matches[0].text, "a1");
+        // 手机号匹配的前缀(1..2)被词典匹配覆盖，裁剪掉；剩余的(2..12)必须保留
+        assert_eq!(matches[1].start, 2);
+        assert_eq!(matches[1].end, 12);
+        assert_eq!(matches[1].text, text[2..12]);
+    }
+
+    /// 运行时新增/删除词条应该原子热更新内存索引，并把结果持久化回`dic.txt`
+    #[tokio::test]
+    async fn add_then_remove_words_round_trips_through_dic_file() {
+        let (filter, base_dir) = test_filter_in_dir(&["原有词"]).await;
+        let dic_path = base_dir.join("models").join("source").join("dic.txt");
+
+        let added = filter.add_words(vec!["新词".to_string()]).await.unwrap();
+        assert_eq!(added.added, 1);
+        assert_eq!(added.skipped, 0);
+
+        // 新增的词无需重建即可立即命中
+        assert_eq!(
+            filter.find_sensitive_words("这里有新词").await,
+            vec!["新词".to_string()]
+        );
+        // 并且持久化回了dic.txt
+        let content = fs::read_to_string(&dic_path).await.unwrap();
+        assert!(content.contains("原有词"));
+        assert!(content.contains("新词"));
+
+        let removed = filter.remove_words(vec!["原有词".to_string()]).await.unwrap();
+        assert_eq!(removed.removed, 1);
+        assert_eq!(removed.skipped, 0);
+
+        // 删除的词不再命中，dic.txt也不再包含它，但新词不受影响
+        assert!(filter.find_sensitive_words("这里有原有词").await.is_empty());
+        let content = fs::read_to_string(&dic_path).await.unwrap();
+        assert!(!content.contains("原有词"));
+        assert!(content.contains("新词"));
+    }
+
+    /// `dic.txt`中`word\tcategory\tseverity`的分类与严重级别应随匹配一起返回，
+    /// 这是`/check`的`min_severity`过滤能够工作的基础
+    #[tokio::test]
+    async fn find_all_matches_carries_category_and_severity() {
+        let (filter, _) = test_filter_in_dir(&["词A\tdefault\tlow", "词B\tcustom\thigh"]).await;
+        let matches = filter.find_all_matches("这里有词A和词B").await;
+        assert_eq!(matches.len(), 2);
+
+        let word_a = matches.iter().find(|m| m.text == "词A").unwrap();
+        assert_eq!(word_a.category, "default");
+        assert_eq!(word_a.severity, Severity::Low);
+
+        let word_b = matches.iter().find(|m| m.text == "词B").unwrap();
+        assert_eq!(word_b.category, "custom");
+        assert_eq!(word_b.severity, Severity::High);
+
+        // 复刻`/check`的min_severity过滤逻辑：只保留严重级别不低于阈值的命中
+        let min = Severity::High;
+        let above_min: Vec<_> = matches.iter().filter(|m| m.severity >= min).collect();
+        assert_eq!(above_min.len(), 1);
+        assert_eq!(above_min[0].text, "词B");
+    }
 }
@@ -1,16 +1,22 @@
 mod ac;
 mod filter;
+mod rules;
 
+use ac::{RestoreMap, Severity};
 use axum::{
-    extract::{DefaultBodyLimit, State},
+    body::Body,
+    extract::{DefaultBodyLimit, Request, State},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
+use axum::routing::delete;
 use clap::Parser;
-use filter::SensitiveFilter;
+use filter::{FilterStream, SensitiveFilter};
+use futures_util::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -37,6 +43,19 @@ struct Args {
     /// 启动时是否重建索引
     #[arg(short, long)]
     rebuild: bool,
+
+    /// 拦截模式下拒绝时返回的提示消息；不设置则/check仅返回命中词列表，不做拦截
+    #[arg(long)]
+    deny_message: Option<String>,
+}
+
+// 应用共享状态
+#[derive(Clone)]
+struct AppState {
+    // 敏感词过滤器
+    filter: Arc<SensitiveFilter>,
+    // 拦截模式下的拒绝提示消息，None表示不开启拦截
+    deny_message: Option<Arc<String>>,
 }
 
 // API 过滤请求结构体
@@ -54,21 +73,114 @@ struct FilterResponse {
 }
 
 // API 检查请求结构体
+//
+// `scope`决定了请求体的形状：
+// - "text"（默认）：`text`为待检查的整段文本
+// - "json_fields"：`json`为一个JSON对象，只扫描`fields`中列出的键
+// - "openai"：`json`为一个chat-completion请求体，只扫描`messages[].content`
 #[derive(Debug, Serialize, Deserialize)]
 struct CheckRequest {
-    // 需要检查的文本
-    text: String,
+    // scope为"text"或省略时使用
+    #[serde(default)]
+    text: Option<String>,
+    // scope为"json_fields"或"openai"时使用
+    #[serde(default)]
+    json: Option<Value>,
+    // scope为"json_fields"时，指定json对象中需要扫描的字段名
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+    // 请求体形状："text"(默认)/"json_fields"/"openai"
+    #[serde(default)]
+    scope: Option<String>,
+    // 只返回严重级别不低于此值的命中（"low"/"medium"/"high"），不设置则返回全部
+    #[serde(default)]
+    min_severity: Option<String>,
 }
 
 // API 检查响应结构体
 #[derive(Debug, Serialize, Deserialize)]
 struct CheckResponse {
-    // 是否包含敏感词
+    // 是否包含（经min_severity过滤后的）敏感词
     contains_sensitive: bool,
-    // 发现的敏感词列表
+    // 发现的敏感词命中详情
+    matches: Vec<CheckMatch>,
+}
+
+// 单次敏感词命中的详情
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckMatch {
+    // 命中所在的片段标识："text"；scope="json_fields"时为字段名；
+    // scope="openai"时为"messages[<index>].content"
+    field: String,
+    // 命中的原始子串
+    word: String,
+    // 命中词的分类
+    category: String,
+    // 命中词的严重级别："low"/"medium"/"high"
+    severity: String,
+    // 命中在其所属片段（而非整个请求体）中的起始字节位置
+    start: usize,
+    // 命中在其所属片段（而非整个请求体）中的结束字节位置
+    end: usize,
+}
+
+// 拦截模式下的拒绝响应结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct DenyResponse {
+    // 拒绝原因，即配置的deny_message
+    error: String,
+}
+
+// API 脱敏请求结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct DesensitizeRequest {
+    // 需要脱敏的文本
+    text: String,
+}
+
+// API 脱敏响应结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct DesensitizeResponse {
+    // 脱敏后的文本，敏感词被替换为占位符
+    text: String,
+    // 占位符到原始敏感词的还原映射
+    restore_map: RestoreMap,
+}
+
+// API 还原请求结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct RestoreRequest {
+    // 包含占位符的脱敏文本
+    text: String,
+    // 脱敏时返回的还原映射
+    restore_map: RestoreMap,
+}
+
+// API 还原响应结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct RestoreResponse {
+    // 还原后的原始文本
+    text: String,
+}
+
+// API 词表更新请求结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct WordsRequest {
+    // 待新增或待删除的敏感词列表
     words: Vec<String>,
 }
 
+// API 词表更新响应结构体
+#[derive(Debug, Serialize, Deserialize)]
+struct WordsResponse {
+    // 实际新增的词数
+    added: usize,
+    // 实际删除的词数
+    removed: usize,
+    // 跳过的词数（重复或不存在）
+    skipped: usize,
+}
+
 // API 状态响应结构体
 #[derive(Debug, Serialize, Deserialize)]
 struct StatusResponse {
@@ -100,15 +212,25 @@ async fn main() -> anyhow::Result<()> {
         filter.init().await?;
     }
 
+    // 组装应用共享状态
+    let state = AppState {
+        filter,
+        deny_message: args.deny_message.map(Arc::new),
+    };
+
     // 构建Web应用路由
     let app = Router::new()
         .route("/filter", post(filter_text))
+        .route("/filter/stream", post(filter_text_stream))
         .route("/check", post(check_text))
+        .route("/desensitize", post(desensitize_text))
+        .route("/restore", post(restore_text))
+        .route("/words", post(add_words).delete(remove_words))
         .route("/rebuild", post(rebuild_index))
         .route("/status", get(status))
         .layer(DefaultBodyLimit::max(1024 * 1024 * 10)) // 限制请求体大小为10MB
         .layer(TraceLayer::new_for_http()) // 添加HTTP请求追踪
-        .with_state(filter); // 注入敏感词过滤器状态
+        .with_state(state); // 注入应用共享状态
 
     // 启动Web服务器
     let addr = format!("{}:{}", args.host, args.port).parse::<SocketAddr>()?;
@@ -121,41 +243,290 @@ async fn main() -> anyhow::Result<()> {
 // 文本过滤API处理函数
 async fn filter_text(
     // 从应用状态获取过滤器
-    State(filter): State<Arc<SensitiveFilter>>,
+    State(state): State<AppState>,
     // 从请求体解析JSON
     Json(request): Json<FilterRequest>,
 ) -> impl IntoResponse {
     // 过滤文本
-    let filtered = filter.filter(&request.text).await;
+    let filtered = state.filter.filter(&request.text).await;
     // 返回过滤后的文本
     Json(FilterResponse { filtered })
 }
 
+// 将新到达的字节并入`pending`（上一个chunk末尾尚未收完的UTF-8序列），解码出
+// 其中所有已经完整的字符，并把新的不完整尾部留在`pending`里等下一个chunk
+//
+// HTTP/SSE的chunk边界不保证落在字符边界上，直接对每个chunk单独做
+// `from_utf8_lossy`会把被截断的多字节字符永久替换成U+FFFD——这是字节层面的
+// 问题，和`FilterStream`已经处理的词级别跨chunk缓冲是两回事，必须在解码前
+// 先解决。真正非法的字节（而不只是被截断）用替换字符标出后跳过，避免因为
+// 一处脏数据卡住整条流。
+fn decode_utf8_chunk(pending: &mut Vec<u8>, new_bytes: &[u8]) -> String {
+    pending.extend_from_slice(new_bytes);
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(s) => {
+                text.push_str(s);
+                pending.clear();
+                break;
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                text.push_str(std::str::from_utf8(&pending[..valid_up_to]).unwrap());
+                match e.error_len() {
+                    Some(bad_len) => {
+                        // 真正非法的字节序列（不只是被截断），标出后跳过继续解析剩余部分
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        pending.drain(..valid_up_to + bad_len);
+                    }
+                    None => {
+                        // 末尾是尚未收完的多字节序列，留到和下一个chunk的字节拼接后再解码
+                        pending.drain(..valid_up_to);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    text
+}
+
+// 流式文本过滤API处理函数
+//
+// 请求体作为不限长度的文本chunk流逐块读入（不等全部到齐），每读入一个chunk
+// 就把其中可以安全flush的前缀过滤后写入响应，借助`FilterStream`维护的尾部
+// 缓冲避免把跨chunk的敏感词拆成两半而漏判。适合在代理LLM输出时边生成边过滤。
+async fn filter_text_stream(State(state): State<AppState>, request: Request) -> impl IntoResponse {
+    let incoming = request.into_body().into_data_stream();
+    let session = FilterStream::new(Arc::clone(&state.filter)).await;
+    // 上一个chunk末尾尚未收完的UTF-8序列，供下一个chunk拼接
+    let pending_bytes: Vec<u8> = Vec::new();
+
+    // 状态为(输入chunk流, 过滤会话, 字节级别的待拼接尾部, 是否已结束)，
+    // 每次产出一个已过滤的输出chunk
+    let output = stream::unfold(
+        (incoming, session, pending_bytes, false),
+        |(mut incoming, mut session, mut pending_bytes, finished)| async move {
+            if finished {
+                return None;
+            }
+            while let Some(chunk) = incoming.next().await {
+                let bytes = match chunk {
+                    Ok(bytes) => bytes,
+                    Err(_) => break,
+                };
+                let text = decode_utf8_chunk(&mut pending_bytes, &bytes);
+                let flushed = session.push(&text).await;
+                if !flushed.is_empty() {
+                    return Some((
+                        Ok::<_, std::io::Error>(axum::body::Bytes::from(flushed)),
+                        (incoming, session, pending_bytes, false),
+                    ));
+                }
+            }
+            // 输入流结束：若还剩未收完的字节序列，说明请求体本身被截断，按有损方式
+            // 解码收尾，不能无限等待永远不会到来的后续字节
+            let mut remaining_text = String::new();
+            if !pending_bytes.is_empty() {
+                remaining_text.push_str(&String::from_utf8_lossy(&pending_bytes));
+            }
+            let flushed = session.push(&remaining_text).await;
+            let tail = session.finish().await;
+            let remaining = flushed + &tail;
+            Some((
+                Ok(axum::body::Bytes::from(remaining)),
+                (incoming, session, Vec::new(), true),
+            ))
+        },
+    );
+
+    Body::from_stream(output)
+}
+
+// 根据`scope`从请求体中提取需要扫描的文本片段，其余字段保持不变
+//
+// 每个片段都带上标识其来源的label：scope="json_fields"时有多个字段、
+// scope="openai"时有多条消息，字节偏移只在各自片段内有意义，调用方必须
+// 知道某个命中来自哪个字段/消息才能定位回原始请求体。
+fn scoped_segments(request: &CheckRequest) -> Result<Vec<(String, String)>, StatusCode> {
+    match request.scope.as_deref().unwrap_or("text") {
+        // 扫描整段文本
+        "text" => {
+            let text = request.text.clone().ok_or(StatusCode::BAD_REQUEST)?;
+            Ok(vec![("text".to_string(), text)])
+        }
+        // 只扫描JSON对象中指定的字段，不触碰其余字段
+        "json_fields" => {
+            let json = request.json.as_ref().ok_or(StatusCode::BAD_REQUEST)?;
+            let fields = request.fields.as_ref().ok_or(StatusCode::BAD_REQUEST)?;
+            let object = json.as_object().ok_or(StatusCode::BAD_REQUEST)?;
+            let segments = fields
+                .iter()
+                .filter_map(|field| object.get(field).map(|value| (field, value)))
+                .filter_map(|(field, value)| value.as_str().map(|s| (field.clone(), s.to_string())))
+                .collect();
+            Ok(segments)
+        }
+        // 只扫描chat-completion请求体中每条消息的content
+        "openai" => {
+            let json = request.json.as_ref().ok_or(StatusCode::BAD_REQUEST)?;
+            let messages = json
+                .get("messages")
+                .and_then(Value::as_array)
+                .ok_or(StatusCode::BAD_REQUEST)?;
+            let segments = messages
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, message)| message.get("content").map(|content| (idx, content)))
+                .filter_map(|(idx, content)| {
+                    content
+                        .as_str()
+                        .map(|s| (format!("messages[{idx}].content"), s.to_string()))
+                })
+                .collect();
+            Ok(segments)
+        }
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
 // 敏感词检查API处理函数
 async fn check_text(
-    // 从应用状态获取过滤器
-    State(filter): State<Arc<SensitiveFilter>>,
+    // 从应用状态获取过滤器与拦截配置
+    State(state): State<AppState>,
     // 从请求体解析JSON
     Json(request): Json<CheckRequest>,
-) -> impl IntoResponse {
-    // 查找文本中的敏感词
-    let words = filter.find_sensitive_words(&request.text).await;
-    // 判断是否含有敏感词
-    let contains_sensitive = !words.is_empty();
-    // 返回检查结果
+) -> axum::response::Response {
+    // 根据scope提取出需要扫描的文本片段
+    let segments = match scoped_segments(&request) {
+        Ok(segments) => segments,
+        Err(status) => return status.into_response(),
+    };
+
+    // 解析min_severity过滤阈值，无法识别的值按Low处理（即不过滤）
+    let min_severity = request.min_severity.as_deref().map(Severity::parse);
+
+    // 只在命中的片段上查找敏感词，未涉及的字段不受影响；按min_severity过滤后
+    // 才计入contains_sensitive/matches，低于阈值的命中既不拦截也不回显
+    let mut matches = Vec::new();
+    for (field, segment) in &segments {
+        for m in state.filter.find_all_matches(segment).await {
+            if let Some(min) = min_severity {
+                if m.severity < min {
+                    continue;
+                }
+            }
+            matches.push(CheckMatch {
+                field: field.clone(),
+                word: m.text,
+                category: m.category,
+                severity: m.severity.as_str().to_string(),
+                start: m.start,
+                end: m.end,
+            });
+        }
+    }
+    let contains_sensitive = !matches.is_empty();
+
+    // 拦截模式：命中敏感词时直接拒绝，返回配置的提示消息
+    if contains_sensitive {
+        if let Some(deny_message) = &state.deny_message {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(DenyResponse {
+                    error: deny_message.as_str().to_string(),
+                }),
+            )
+                .into_response();
+        }
+    }
+
+    // 未开启拦截模式，或未命中敏感词：返回常规检查结果
     Json(CheckResponse {
         contains_sensitive,
-        words,
+        matches,
     })
+    .into_response()
+}
+
+// 脱敏API处理函数
+async fn desensitize_text(
+    // 从应用状态获取过滤器
+    State(state): State<AppState>,
+    // 从请求体解析JSON
+    Json(request): Json<DesensitizeRequest>,
+) -> impl IntoResponse {
+    // 脱敏文本，敏感词被替换为占位符
+    let (text, restore_map) = state.filter.desensitize(&request.text).await;
+    // 返回脱敏后的文本及还原映射
+    Json(DesensitizeResponse { text, restore_map })
+}
+
+// 还原API处理函数
+async fn restore_text(
+    // 从应用状态获取过滤器
+    State(state): State<AppState>,
+    // 从请求体解析JSON
+    Json(request): Json<RestoreRequest>,
+) -> impl IntoResponse {
+    // 使用还原映射将占位符替换回原始敏感词
+    let text = state.filter.restore(&request.text, &request.restore_map);
+    // 返回还原后的文本
+    Json(RestoreResponse { text })
+}
+
+// 新增敏感词API处理函数
+async fn add_words(
+    // 从应用状态获取过滤器
+    State(state): State<AppState>,
+    // 从请求体解析JSON
+    Json(request): Json<WordsRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // 尝试新增敏感词
+    match state.filter.add_words(request.words).await {
+        Ok(result) => Ok(Json(WordsResponse {
+            added: result.added,
+            removed: result.removed,
+            skipped: result.skipped,
+        })),
+        Err(e) => {
+            // 记录新增失败的原因
+            warn!("Failed to add words: {:?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除敏感词API处理函数
+async fn remove_words(
+    // 从应用状态获取过滤器
+    State(state): State<AppState>,
+    // 从请求体解析JSON
+    Json(request): Json<WordsRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // 尝试删除敏感词
+    match state.filter.remove_words(request.words).await {
+        Ok(result) => Ok(Json(WordsResponse {
+            added: result.added,
+            removed: result.removed,
+            skipped: result.skipped,
+        })),
+        Err(e) => {
+            // 记录删除失败的原因
+            warn!("Failed to remove words: {:?}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 // 重建索引API处理函数
 async fn rebuild_index(
     // 从应用状态获取过滤器
-    State(filter): State<Arc<SensitiveFilter>>,
+    State(state): State<AppState>,
 ) -> Result<impl IntoResponse, StatusCode> {
     // 尝试重建索引
-    match filter.rebuild_index().await {
+    match state.filter.rebuild_index().await {
         Ok(_) => Ok(Json(StatusResponse {
             status: "Index rebuilt successfully".to_string(),
         })),
@@ -175,3 +546,97 @@ async fn status() -> impl IntoResponse {
         status: "Service is running".to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn test_state(words: &[&str]) -> AppState {
+        let base_dir = std::env::temp_dir().join(format!(
+            "sensitive_word_main_test_{}_{}",
+            std::process::id(),
+            words.join("_")
+        ));
+        let filter = SensitiveFilter::new(&base_dir).await.unwrap();
+        let dic_path = base_dir.join("models").join("source").join("dic.txt");
+        tokio::fs::write(&dic_path, words.join("\n")).await.unwrap();
+        filter.rebuild_index().await.unwrap();
+        AppState {
+            filter: Arc::new(filter),
+            deny_message: None,
+        }
+    }
+
+    /// scope="json_fields"只扫描`fields`中列出的键，每个片段都带上字段名标签
+    #[test]
+    fn scoped_segments_labels_json_fields_by_field_name() {
+        let request = CheckRequest {
+            text: None,
+            json: Some(json!({"title": "正常标题", "body": "包含敏感词的正文"})),
+            fields: Some(vec!["body".to_string()]),
+            scope: Some("json_fields".to_string()),
+            min_severity: None,
+        };
+        let segments = scoped_segments(&request).unwrap();
+        assert_eq!(segments, vec![("body".to_string(), "包含敏感词的正文".to_string())]);
+    }
+
+    /// scope="openai"只扫描每条消息的content，标签为"messages[<index>].content"
+    #[test]
+    fn scoped_segments_labels_openai_messages_by_index() {
+        let request = CheckRequest {
+            text: None,
+            json: Some(json!({
+                "messages": [
+                    {"role": "system", "content": "系统提示"},
+                    {"role": "user", "content": "用户问题"}
+                ]
+            })),
+            fields: None,
+            scope: Some("openai".to_string()),
+            min_severity: None,
+        };
+        let segments = scoped_segments(&request).unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                ("messages[0].content".to_string(), "系统提示".to_string()),
+                ("messages[1].content".to_string(), "用户问题".to_string()),
+            ]
+        );
+    }
+
+    /// 拦截模式下命中敏感词应返回403与配置的拒绝消息，而不是常规的检查结果
+    #[tokio::test]
+    async fn check_text_denies_when_deny_mode_enabled_and_word_matches() {
+        let mut state = test_state(&["敏感词"]).await;
+        state.deny_message = Some(Arc::new("内容违规".to_string()));
+
+        let request = CheckRequest {
+            text: Some("这里有敏感词".to_string()),
+            json: None,
+            fields: None,
+            scope: None,
+            min_severity: None,
+        };
+        let response = check_text(State(state), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    /// 未开启拦截模式时，即使命中敏感词也应返回常规的200检查结果
+    #[tokio::test]
+    async fn check_text_returns_ok_without_deny_mode() {
+        let state = test_state(&["敏感词"]).await;
+
+        let request = CheckRequest {
+            text: Some("这里有敏感词".to_string()),
+            json: None,
+            fields: None,
+            scope: None,
+            min_severity: None,
+        };
+        let response = check_text(State(state), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexSet};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::fs;
+use tracing::info;
+
+/// 内置GROK宏名 -> 正则表达式 的映射表
+fn grok_registry() -> &'static HashMap<&'static str, &'static str> {
+    static REGISTRY: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut m = HashMap::new();
+        // 中国大陆手机号
+        m.insert("MOBILE", r"1[3-9]\d{9}");
+        // 邮箱地址
+        m.insert("EMAIL", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}");
+        // 中国大陆18位身份证号
+        m.insert("IDCARD", r"\d{17}[\dXx]");
+        m
+    })
+}
+
+/// 内置GROK宏命中文本的最大字节长度估算，用于流式过滤计算跨chunk安全边界
+fn grok_macro_max_len(name: &str) -> Option<usize> {
+    match name {
+        "MOBILE" => Some(11),
+        // RFC 5321对邮箱长度的上限，保守估计
+        "EMAIL" => Some(254),
+        "IDCARD" => Some(18),
+        _ => None,
+    }
+}
+
+/// 自定义正则无法静态计算匹配长度时的保守默认估算
+const DEFAULT_PATTERN_MAX_LEN: usize = 256;
+
+/// 估算一条规则命中文本的最大字节长度
+///
+/// 仅内置GROK宏有预设的长度估算（EMAIL取的是保守上限，并非精确值）；规则若只引用单个内置宏则直接复用该长度，
+/// 否则（自定义正则，或宏与其他字面量混合）无法静态计算，退回保守默认值，
+/// 宁可多留一点跨chunk缓冲也不要让命中被从中间切断而漏判。
+fn estimate_pattern_max_len(raw_pattern: &str) -> usize {
+    static GROK_TOKEN: OnceLock<Regex> = OnceLock::new();
+    let token_re = GROK_TOKEN.get_or_init(|| Regex::new(r"^%\{(\w+)\}$").unwrap());
+    if let Some(caps) = token_re.captures(raw_pattern.trim()) {
+        if let Some(len) = grok_macro_max_len(&caps[1]) {
+            return len;
+        }
+    }
+    DEFAULT_PATTERN_MAX_LEN
+}
+
+/// 展开GROK宏，例如将`%{MOBILE}`替换为内置的手机号正则
+fn expand_grok(pattern: &str) -> Result<String> {
+    static GROK_TOKEN: OnceLock<Regex> = OnceLock::new();
+    let token_re = GROK_TOKEN.get_or_init(|| Regex::new(r"%\{(\w+)\}").unwrap());
+
+    let registry = grok_registry();
+    let mut error = None;
+    let expanded = token_re.replace_all(pattern, |caps: &regex::Captures| {
+        let name = &caps[1];
+        match registry.get(name) {
+            Some(expr) => (*expr).to_string(),
+            None => {
+                error = Some(anyhow::anyhow!("unknown GROK macro %{{{name}}}"));
+                String::new()
+            }
+        }
+    });
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+    Ok(expanded.into_owned())
+}
+
+/// 基于正则/GROK规则的结构化PII匹配引擎，作为AC词典匹配的补充
+///
+/// 词典只能命中固定的敏感词，而手机号、邮箱、身份证号这类结构化信息更适合
+/// 用正则描述。规则文件中每行以`role=pattern`的形式声明一个规则，pattern既
+/// 可以是原始正则，也可以引用`%{MOBILE}`这类内置GROK宏。
+pub struct RuleEngine {
+    // 用于快速判断文本是否命中任意规则，避免对每条规则都做一次查找
+    set: RegexSet,
+    // 与set下标一一对应，用于定位具体匹配跨度
+    regexes: Vec<Regex>,
+    // 与regexes下标一一对应的角色名，如"mobile"、"email"
+    roles: Vec<String>,
+    // 所有规则中估算的最大命中长度，供流式过滤计算跨chunk安全边界
+    max_pattern_len: usize,
+}
+
+impl RuleEngine {
+    /// 解析`rules.txt`的文本内容并构建规则引擎
+    pub fn from_rules_text(content: &str) -> Result<Self> {
+        let mut roles = Vec::new();
+        let mut patterns = Vec::new();
+        let mut max_pattern_len = 0usize;
+
+        for (lineno, line) in content.lines().enumerate() {
+            let line = line.trim();
+            // 跳过空行和注释行
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (role, raw_pattern) = line
+                .split_once('=')
+                .with_context(|| format!("invalid rule at line {}: missing '='", lineno + 1))?;
+            let raw_pattern = raw_pattern.trim();
+            let pattern = expand_grok(raw_pattern)
+                .with_context(|| format!("invalid rule at line {}", lineno + 1))?;
+            max_pattern_len = max_pattern_len.max(estimate_pattern_max_len(raw_pattern));
+            roles.push(role.trim().to_string());
+            patterns.push(pattern);
+        }
+
+        if patterns.is_empty() {
+            return Err(anyhow::anyhow!("no rules found in rules.txt"));
+        }
+
+        // RegexSet用于一次性判断命中了哪些规则
+        let set = RegexSet::new(&patterns)?;
+        // 逐条编译为独立的Regex，用于提取具体的匹配跨度
+        let regexes = patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<std::result::Result<Vec<_>, regex::Error>>()?;
+
+        info!("Loaded {} rules into rule engine", roles.len());
+        Ok(Self {
+            set,
+            regexes,
+            roles,
+            max_pattern_len,
+        })
+    }
+
+    /// 所有规则中估算的最大命中长度，供流式过滤计算跨chunk安全边界
+    pub fn max_pattern_len(&self) -> usize {
+        self.max_pattern_len
+    }
+
+    /// 从`source_dir`下的`rules.txt`加载规则引擎
+    pub async fn load_from_dir<P: AsRef<Path>>(source_dir: P) -> Result<Self> {
+        let path = source_dir.as_ref().join("rules.txt");
+        let content = fs::read_to_string(&path).await?;
+        Self::from_rules_text(&content)
+    }
+
+    /// 在文本中查找所有规则匹配，返回(起始位置, 结束位置, 角色名)
+    pub fn find_matches(&self, text: &str) -> Vec<(usize, usize, String)> {
+        // 先用RegexSet快速判断命中了哪些规则，未命中的规则无需再做查找
+        let mut matches = Vec::new();
+        for idx in self.set.matches(text).into_iter() {
+            let re = &self.regexes[idx];
+            let role = &self.roles[idx];
+            for mat in re.find_iter(text) {
+                matches.push((mat.start(), mat.end(), role.clone()));
+            }
+        }
+        matches
+    }
+}